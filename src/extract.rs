@@ -0,0 +1,59 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use worker::*;
+
+use crate::Params;
+
+/// Builds `Self` out of an incoming request, so route handlers can declare
+/// typed arguments instead of parsing `Request`/`Params` by hand.
+///
+/// Extraction failures turn into an automatic `400 Bad Request`; see
+/// [`crate::Router`]'s extractor-based `get`/`post`/etc. overloads.
+#[async_trait(?Send)]
+pub trait FromRequest<State>: Sized {
+    async fn from_request(req: &mut Request, state: &Arc<State>, params: &Params) -> Result<Self>;
+}
+
+/// Extracts and deserializes the request body as JSON.
+pub struct Json<T>(pub T);
+
+#[async_trait(?Send)]
+impl<State, T: DeserializeOwned> FromRequest<State> for Json<T> {
+    async fn from_request(req: &mut Request, _state: &Arc<State>, _params: &Params) -> Result<Self> {
+        req.json().await.map(Json)
+    }
+}
+
+/// Extracts and deserializes the URL query string.
+pub struct Query<T>(pub T);
+
+#[async_trait(?Send)]
+impl<State, T: DeserializeOwned> FromRequest<State> for Query<T> {
+    async fn from_request(req: &mut Request, _state: &Arc<State>, _params: &Params) -> Result<Self> {
+        req.query().map(Query)
+    }
+}
+
+/// Extracts and deserializes the matched route's [`Params`].
+pub struct Path<T>(pub T);
+
+#[async_trait(?Send)]
+impl<State, T: DeserializeOwned> FromRequest<State> for Path<T> {
+    async fn from_request(_req: &mut Request, _state: &Arc<State>, params: &Params) -> Result<Self> {
+        serde_urlencoded::from_str(&params.to_query_string())
+            .map(Path)
+            .map_err(|err| Error::RustError(err.to_string()))
+    }
+}
+
+/// Extracts the router's shared state.
+pub struct State<S>(pub Arc<S>);
+
+#[async_trait(?Send)]
+impl<S> FromRequest<S> for State<S> {
+    async fn from_request(_req: &mut Request, state: &Arc<S>, _params: &Params) -> Result<Self> {
+        Ok(State(Arc::clone(state)))
+    }
+}
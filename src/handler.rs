@@ -0,0 +1,55 @@
+//! Bridges the base `Fn(Request, Arc<State>, Params)` handler shape with
+//! handlers that declare one to four [`FromRequest`] extractors instead.
+use std::future::Future;
+use std::sync::Arc;
+
+use worker::*;
+
+use crate::{FromRequest, Params, ResponseFuture};
+
+/// A route handler. `Args` is a marker type that distinguishes the base
+/// 3-arg form from the extractor-based ones below; callers never name it
+/// themselves, it's inferred from the handler function's own signature.
+pub trait Handler<State, Args> {
+    fn call(&self, req: Request, state: Arc<State>, params: Params) -> ResponseFuture;
+}
+
+impl<State, F, Res> Handler<State, (Request, Arc<State>, Params)> for F
+where
+    F: Fn(Request, Arc<State>, Params) -> Res + 'static,
+    Res: Future<Output = Result<Response>> + 'static,
+{
+    fn call(&self, req: Request, state: Arc<State>, params: Params) -> ResponseFuture {
+        Box::pin(self(req, state, params))
+    }
+}
+
+macro_rules! impl_handler {
+    ($($ty:ident $var:ident),+) => {
+        impl<State, F, Res, $($ty),+> Handler<State, (Request, Arc<State>, Params, $($ty),+)> for F
+        where
+            State: 'static,
+            F: Fn($($ty),+) -> Res + Clone + 'static,
+            Res: Future<Output = Result<Response>> + 'static,
+            $($ty: FromRequest<State> + 'static),+
+        {
+            fn call(&self, mut req: Request, state: Arc<State>, params: Params) -> ResponseFuture {
+                let handler = self.clone();
+                Box::pin(async move {
+                    $(
+                        let $var = match $ty::from_request(&mut req, &state, &params).await {
+                            Ok(value) => value,
+                            Err(_) => return ResponseBuilder::new().error("bad request", 400),
+                        };
+                    )+
+                    handler($($var),+).await
+                })
+            }
+        }
+    };
+}
+
+impl_handler!(A a);
+impl_handler!(A a, B b);
+impl_handler!(A a, B b, C c);
+impl_handler!(A a, B b, C c, D d);
@@ -4,7 +4,7 @@
 //! ```rust
 //! struct ServerState {}
 //!
-//! async fn get_hello(_req: Request, _state: Arc<ServerState>) -> Result<Response> {
+//! async fn get_hello(_req: Request, _state: Arc<ServerState>, _params: Params) -> Result<Response> {
 //!   ResponseBuilder::new().ok("hello")
 //! }
 //!
@@ -18,14 +18,32 @@
 //! ```
 //!
 //! [`worker`]: https://crates.io/crates/worker
+mod extract;
+mod handler;
+mod middleware;
+mod params;
+mod scope;
+mod trie;
+
+pub use extract::{FromRequest, Json, Path, Query, State};
+pub use handler::Handler;
+pub use middleware::{Middleware, Next};
+pub use params::Params;
+pub use scope::Scope;
+
 use std::future::Future;
 use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::Arc;
+use trie::Trie;
 use urlpattern::{UrlPattern, UrlPatternInit, UrlPatternMatchInput};
 use worker::*;
 
 /// Route pattern
-pub struct Pattern(urlpattern::UrlPattern);
+pub struct Pattern {
+    matcher: urlpattern::UrlPattern,
+    pathname: String,
+}
 
 /// Construct a route pattern using a URL path
 /// Examples:
@@ -39,38 +57,109 @@ pub fn path(v: &str) -> Result<Pattern> {
         ..Default::default()
     };
 
-    let pattern = <UrlPattern>::parse(init, Default::default())
+    let matcher = <UrlPattern>::parse(init, Default::default())
         .map_err(|err| Error::RustError(format!("failed to parse route pattern: {err}")))?;
-    Ok(Pattern(pattern))
+    Ok(Pattern {
+        matcher,
+        pathname: v.to_owned(),
+    })
 }
 
-type Handler<State> = Box<dyn Fn(Request, Arc<State>) -> ResponseFuture + 'static>;
+impl Pattern {
+    /// Whether this pattern only uses the subset of `urlpattern` syntax the
+    /// radix tree's naive `:name`/`*name` tokenizer understands: a param or
+    /// wildcard must start a path segment (right after `/`, or at index 0),
+    /// have a name, and run all the way to the next `/` (or the end of the
+    /// pattern) with nothing else packed into the same segment — plus only a
+    /// single, trailing wildcard. Optional/repeat modifiers (`:id?`, `:x+`,
+    /// `:x*`), custom regex groups, anonymous captures (bare `:`/`*`) and a
+    /// param/wildcard followed by more literal text in the same segment
+    /// (`:id.json`) all use shapes the tokenizer can't represent and must go
+    /// through [`UrlPattern`]'s own matching instead, or their semantics get
+    /// silently dropped or corrupted (e.g. `:id?` becoming a required
+    /// `"id?"` param, or `:id.json` capturing `"42.json"` as `id` and
+    /// matching `/users/42.txt` too).
+    fn is_trie_eligible(&self) -> bool {
+        let path = self.pathname.as_str();
+        if path.contains(['?', '+', '{', '}', '(', ')']) {
+            return false;
+        }
+
+        for (i, marker) in path.match_indices([':', '*']) {
+            if i != 0 && path.as_bytes()[i - 1] != b'/' {
+                return false;
+            }
+
+            let name_start = i + 1;
+            let name_len = name_len(&path[name_start..]);
+            if name_len == 0 {
+                return false;
+            }
+
+            let name_end = name_start + name_len;
+            match path[name_end..].chars().next() {
+                None | Some('/') => {}
+                _ => return false,
+            }
+            if marker == "*" && path[name_end..].contains('/') {
+                return false;
+            }
+        }
+
+        true
+    }
+}
+
+/// Length, in bytes, of the leading run of `s` that `urlpattern` considers
+/// valid `:name`/`*name` characters (approximating its unicode
+/// identifier-plus-`$` rule with Unicode alphanumerics, `_` and `$`, which
+/// covers real-world route param names).
+fn name_len(s: &str) -> usize {
+    let mut len = 0;
+    for (i, c) in s.char_indices() {
+        if !(c.is_alphanumeric() || c == '_' || c == '$') {
+            break;
+        }
+        len = i + c.len_utf8();
+    }
+    len
+}
+
+type BoxedHandler<State> = Box<dyn Fn(Request, Arc<State>, Params) -> ResponseFuture + 'static>;
 pub type ResponseFuture = Pin<Box<dyn Future<Output = Result<Response>> + 'static>>;
 
-struct Route<State> {
+/// A route whose pattern uses regex/advanced `urlpattern` features and so
+/// cannot be mounted on the radix tree; matched with a linear scan instead.
+struct FallbackRoute<State> {
     pattern: Pattern,
-    handler: Handler<State>,
+    handler: BoxedHandler<State>,
     method: Method,
 }
 
 /// HTTP router
 pub struct Router<State> {
     state: Arc<State>,
-    routes: Vec<Route<State>>,
+    trie: Trie<State>,
+    fallback_routes: Vec<FallbackRoute<State>>,
+    method_not_allowed: bool,
+    middlewares: Vec<Rc<dyn Middleware<State>>>,
+    fallback: Option<BoxedHandler<State>>,
 }
 
 macro_rules! insert_method {
     ($name:ident, $method:expr) => {
         /// Register a new request handler for the HTTP method.
         ///
-        /// The request handler has the following type:
+        /// The request handler either takes the base
+        /// `(Request, Arc<State>, Params)` form:
         /// ```rust
-        /// async fn handler(_req: worker::Request, _state: Arc<State>) -> Result<worker::Response>;
+        /// async fn handler(_req: worker::Request, _state: Arc<State>, _params: Params) -> Result<worker::Response>;
         /// ```
-        pub fn $name<HandlerFn, Res>(self, pattern: Pattern, handler: HandlerFn) -> Self
+        /// or one to four [`FromRequest`] extractors, e.g. `async fn handler(Json(body): Json<Body>) -> Result<worker::Response>`.
+        pub fn $name<H, Args>(self, pattern: Pattern, handler: H) -> Self
         where
-            HandlerFn: Fn(Request, Arc<State>) -> Res + 'static,
-            Res: Future<Output = Result<Response>> + 'static,
+            H: Handler<State, Args> + 'static,
+            Args: 'static,
         {
             self.insert($method, pattern, handler)
         }
@@ -82,26 +171,67 @@ impl<State> Router<State> {
     /// The state will be passed in every request handler.
     pub fn new_with_state(state: Arc<State>) -> Self {
         Router {
-            routes: vec![],
             state,
+            trie: Trie::default(),
+            fallback_routes: vec![],
+            method_not_allowed: true,
+            middlewares: vec![],
+            fallback: None,
         }
     }
 
-    fn insert<HandlerFn, Res>(
-        mut self,
-        method: Method,
-        pattern: Pattern,
-        handler: HandlerFn,
-    ) -> Self
+    /// Override the default `404 Not Found` response served when no route
+    /// (and, if [`Router::method_not_allowed`] applies, no `Allow` header)
+    /// matches the request. Runs through the router's middleware chain like
+    /// any other handler, with empty [`Params`].
+    pub fn fallback<H, Args>(mut self, handler: H) -> Self
+    where
+        H: Handler<State, Args> + 'static,
+        Args: 'static,
+    {
+        self.fallback = Some(Box::new(move |req, state, params| handler.call(req, state, params)));
+        self
+    }
+
+    /// Toggle whether a path that matches some route, but not for the
+    /// requested method, responds `405 Method Not Allowed` with an `Allow`
+    /// header (the default) instead of the generic `404`.
+    pub fn method_not_allowed(mut self, enabled: bool) -> Self {
+        self.method_not_allowed = enabled;
+        self
+    }
+
+    /// Push a [`Middleware`] onto the chain run around every matched route,
+    /// in registration order.
+    pub fn with(mut self, middleware: impl Middleware<State> + 'static) -> Self {
+        self.middlewares.push(Rc::new(middleware));
+        self
+    }
+
+    fn insert<H, Args>(self, method: Method, pattern: Pattern, handler: H) -> Self
     where
-        HandlerFn: Fn(Request, Arc<State>) -> Res + 'static,
-        Res: Future<Output = Result<Response>> + 'static,
+        H: Handler<State, Args> + 'static,
+        Args: 'static,
     {
-        self.routes.push(Route {
-            method,
-            pattern,
-            handler: Box::new(move |req, state| Box::pin(handler(req, state))),
-        });
+        let handler: BoxedHandler<State> =
+            Box::new(move |req, state, params| handler.call(req, state, params));
+        self.mount(method, pattern, handler)
+    }
+
+    /// Mount an already-boxed `handler` on the trie or the fallback list,
+    /// depending on whether `pattern` needs regex matching. Shared by
+    /// [`Router::insert`] and [`Scope`].
+    pub(crate) fn mount(mut self, method: Method, pattern: Pattern, handler: BoxedHandler<State>) -> Self {
+        if pattern.is_trie_eligible() {
+            self.trie.insert(method, &pattern.pathname, handler);
+        } else {
+            self.fallback_routes.push(FallbackRoute {
+                method,
+                pattern,
+                handler,
+            });
+        }
+
         self
     }
 
@@ -117,22 +247,125 @@ impl<State> Router<State> {
 
     pub async fn run(&self, req: Request) -> Result<Response> {
         let url = req.url()?;
+        let method = req.method();
 
-        for route in &self.routes {
-            if route.method != req.method() {
+        if let Some((handler, params)) = self.trie.lookup(&method, url.path()) {
+            return self.dispatch(handler, params).run(req).await;
+        }
+
+        for route in &self.fallback_routes {
+            if route.method != method {
                 continue;
             }
 
-            if let Some(_res) = route
+            if let Some(res) = route
                 .pattern
-                .0
+                .matcher
                 .exec(UrlPatternMatchInput::Url(url.clone()))
                 .unwrap()
             {
-                return (route.handler)(req, Arc::clone(&self.state)).await;
+                let params = Params::new(res.pathname.groups);
+                return self.dispatch(&route.handler, params).run(req).await;
+            }
+        }
+
+        if self.method_not_allowed {
+            let allowed = self.allowed_methods(&url);
+            if !allowed.is_empty() {
+                let allow = allowed
+                    .iter()
+                    .map(AsRef::as_ref)
+                    .collect::<Vec<_>>()
+                    .join(", ");
+
+                // No explicit OPTIONS handler matched above, so answer it ourselves.
+                if method == Method::Options {
+                    return Ok(ResponseBuilder::new().with_header("Allow", &allow)?.empty());
+                }
+
+                return ResponseBuilder::new()
+                    .with_header("Allow", &allow)?
+                    .error("method not allowed", 405);
             }
         }
 
+        if let Some(fallback) = &self.fallback {
+            return self.dispatch(fallback, Params::default()).run(req).await;
+        }
+
         ResponseBuilder::new().error("page not found", 404)
     }
+
+    /// Build the middleware chain that leads to a matched `handler`.
+    fn dispatch<'a>(&'a self, handler: &'a BoxedHandler<State>, params: Params) -> Next<'a, State> {
+        Next {
+            middlewares: &self.middlewares,
+            state: Arc::clone(&self.state),
+            params,
+            handler,
+        }
+    }
+
+    /// Methods accepted for `url`'s path, across both the trie and the
+    /// fallback routes, in canonical `Method::all()` order.
+    fn allowed_methods(&self, url: &Url) -> Vec<Method> {
+        let mut matched: std::collections::HashSet<Method> =
+            self.trie.methods_matching(url.path()).into_iter().collect();
+
+        for route in &self.fallback_routes {
+            if route
+                .pattern
+                .matcher
+                .exec(UrlPatternMatchInput::Url(url.clone()))
+                .unwrap()
+                .is_some()
+            {
+                matched.insert(route.method.clone());
+            }
+        }
+
+        Method::all()
+            .into_iter()
+            .filter(|method| matched.contains(method))
+            .collect()
+    }
+}
+
+impl<State: 'static> Router<State> {
+    /// Start building a group of routes mounted under `prefix`, optionally
+    /// sharing their own middleware stack. See [`Scope`].
+    pub fn scope(self, prefix: &str) -> Scope<State> {
+        Scope::new(self, prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn trie_eligible_patterns() {
+        assert!(path("/users").unwrap().is_trie_eligible());
+        assert!(path("/users/:id").unwrap().is_trie_eligible());
+        assert!(path("/files/*path").unwrap().is_trie_eligible());
+    }
+
+    #[test]
+    fn trie_ineligible_patterns() {
+        // Optional/repeat modifiers need UrlPattern's own matching.
+        assert!(!path("/books/:id?").unwrap().is_trie_eligible());
+        assert!(!path("/books/:id+").unwrap().is_trie_eligible());
+        assert!(!path("/books/:id*").unwrap().is_trie_eligible());
+        // A wildcard must be the trailing path segment, not followed by
+        // more literal structure our trie's wildcard node can't represent.
+        assert!(!path("/a/*rest/b").unwrap().is_trie_eligible());
+        // A param followed by more literal text in the same segment can't
+        // be represented either: the trie only knows how to capture up to
+        // the next `/`, so the literal suffix would get silently swallowed
+        // into the param value (and its requirement dropped).
+        assert!(!path("/users/:id.json").unwrap().is_trie_eligible());
+        // Anonymous captures: the trie has nowhere to put a nameless
+        // param/wildcard that matches `UrlPattern`'s own numeric-index key.
+        assert!(!path("/files/*").unwrap().is_trie_eligible());
+    }
 }
@@ -0,0 +1,72 @@
+use std::rc::Rc;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use worker::*;
+
+use crate::{BoxedHandler, Params};
+
+/// Cross-cutting request/response logic (auth, logging, CORS, timing, ...)
+/// that runs before and/or after a matched route handler.
+///
+/// Implementations call `next.run(req).await` to continue the chain, or
+/// return a `Response` directly to short-circuit it (e.g. a 401 that never
+/// reaches the handler).
+#[async_trait(?Send)]
+pub trait Middleware<State> {
+    async fn handle(&self, req: Request, state: Arc<State>, next: Next<'_, State>) -> Result<Response>;
+}
+
+/// The remainder of the middleware chain, plus the matched route handler it
+/// ultimately leads to.
+pub struct Next<'a, State> {
+    pub(crate) middlewares: &'a [Rc<dyn Middleware<State>>],
+    pub(crate) state: Arc<State>,
+    pub(crate) params: Params,
+    pub(crate) handler: &'a BoxedHandler<State>,
+}
+
+impl<'a, State> Next<'a, State> {
+    /// Run the next middleware in the chain, or the route handler once the
+    /// chain is exhausted.
+    pub async fn run(self, req: Request) -> Result<Response> {
+        match self.middlewares.split_first() {
+            Some((middleware, rest)) => {
+                let next = Next {
+                    middlewares: rest,
+                    state: Arc::clone(&self.state),
+                    params: self.params,
+                    handler: self.handler,
+                };
+                middleware.handle(req, self.state, next).await
+            }
+            None => (self.handler)(req, self.state, self.params).await,
+        }
+    }
+}
+
+/// Wrap `inner` so that every call first runs `middlewares`, scoped to just
+/// that handler instead of the whole router.
+pub(crate) fn compose<State: 'static>(
+    middlewares: Vec<Rc<dyn Middleware<State>>>,
+    inner: BoxedHandler<State>,
+) -> BoxedHandler<State> {
+    if middlewares.is_empty() {
+        return inner;
+    }
+
+    let inner = Rc::new(inner);
+    Box::new(move |req, state, params| {
+        let middlewares = middlewares.clone();
+        let inner = Rc::clone(&inner);
+        Box::pin(async move {
+            let next = Next {
+                middlewares: &middlewares,
+                state,
+                params,
+                handler: &inner,
+            };
+            next.run(req).await
+        })
+    })
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+/// Path parameters captured from a matched route.
+///
+/// For a route registered with [`path`](crate::path)`("/users/:id")`, a
+/// request to `/users/42` yields a `Params` with `"id"` mapped to `"42"`.
+/// A `*name` wildcard's capture is keyed by its name; unnamed captures
+/// (only possible on routes matched through `urlpattern`) are keyed by
+/// their numeric index.
+#[derive(Debug, Clone, Default)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub(crate) fn new(groups: HashMap<String, Option<String>>) -> Self {
+        Params(
+            groups
+                .into_iter()
+                .filter_map(|(key, value)| value.map(|value| (key, value)))
+                .collect(),
+        )
+    }
+
+    pub(crate) fn from_captures(captures: Vec<(String, String)>) -> Self {
+        Params(captures.into_iter().collect())
+    }
+
+    /// Get the raw string value of a captured parameter.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    /// Get a captured parameter parsed into `T`, e.g. `params.get_parsed::<u32>("id")`.
+    pub fn get_parsed<T: FromStr>(&self, key: &str) -> Option<T> {
+        self.get(key)?.parse().ok()
+    }
+
+    /// Represent the captures as a `application/x-www-form-urlencoded`
+    /// string, so they can be deserialized into a typed struct with the
+    /// same string-to-type coercion `serde_urlencoded` gives query strings
+    /// (see [`crate::Path`]).
+    pub(crate) fn to_query_string(&self) -> String {
+        serde_urlencoded::to_string(&self.0).unwrap_or_default()
+    }
+}
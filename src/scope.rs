@@ -0,0 +1,98 @@
+use std::rc::Rc;
+
+use worker::*;
+
+use crate::middleware::compose;
+use crate::{path, BoxedHandler, Handler, Middleware, Pattern, Router};
+
+/// A group of routes mounted under a shared path prefix, with their own
+/// middleware stack that only wraps routes registered on this `Scope`.
+///
+/// Created via [`Router::scope`]; nested scopes compose their prefix and
+/// inherit their parent's middleware stack, e.g.
+/// `router.scope("/api").scope("/v1").get(path("/users")?, list_users)`
+/// mounts `list_users` at `/api/v1/users`.
+pub struct Scope<State> {
+    router: Router<State>,
+    prefix: String,
+    middlewares: Vec<Rc<dyn Middleware<State>>>,
+}
+
+macro_rules! insert_method {
+    ($name:ident, $method:expr) => {
+        /// Register a new request handler for the HTTP method, mounted
+        /// under this scope's prefix.
+        pub fn $name<H, Args>(self, pattern: Pattern, handler: H) -> Self
+        where
+            H: Handler<State, Args> + 'static,
+            Args: 'static,
+        {
+            self.insert($method, pattern, handler)
+        }
+    };
+}
+
+impl<State: 'static> Scope<State> {
+    pub(crate) fn new(router: Router<State>, prefix: &str) -> Self {
+        Scope {
+            router,
+            prefix: prefix.to_owned(),
+            middlewares: vec![],
+        }
+    }
+
+    /// Push a [`Middleware`] onto this scope's chain. Only applies to
+    /// routes registered on this scope (and any further nested scopes),
+    /// not the router as a whole.
+    pub fn with(mut self, middleware: impl Middleware<State> + 'static) -> Self {
+        self.middlewares.push(Rc::new(middleware));
+        self
+    }
+
+    /// Start a nested scope, whose prefix is appended to this one's and
+    /// which inherits this scope's middleware stack.
+    pub fn scope(self, prefix: &str) -> Scope<State> {
+        Scope {
+            router: self.router,
+            prefix: format!("{}{prefix}", self.prefix),
+            middlewares: self.middlewares,
+        }
+    }
+
+    fn insert<H, Args>(mut self, method: Method, pattern: Pattern, handler: H) -> Self
+    where
+        H: Handler<State, Args> + 'static,
+        Args: 'static,
+    {
+        let pattern = prefixed(&self.prefix, &pattern);
+        let handler: BoxedHandler<State> =
+            Box::new(move |req, state, params| handler.call(req, state, params));
+        let handler = compose(self.middlewares.clone(), handler);
+
+        self.router = self.router.mount(method, pattern, handler);
+        self
+    }
+
+    insert_method!(head, Method::Head);
+    insert_method!(get, Method::Get);
+    insert_method!(post, Method::Post);
+    insert_method!(put, Method::Put);
+    insert_method!(patch, Method::Patch);
+    insert_method!(delete, Method::Delete);
+    insert_method!(options, Method::Options);
+    insert_method!(connect, Method::Connect);
+    insert_method!(trace, Method::Trace);
+
+    /// Finish building this scope and return the underlying [`Router`].
+    pub fn done(self) -> Router<State> {
+        self.router
+    }
+}
+
+/// Rebuild `pattern` with `prefix` prepended to its pathname, re-parsing
+/// through [`crate::path`] so the resulting `Pattern` matches the full
+/// mounted path rather than just the route's own suffix.
+fn prefixed(prefix: &str, pattern: &Pattern) -> Pattern {
+    let full = format!("{prefix}{}", pattern.pathname);
+    path(&full).expect("invalid scoped route pattern")
+}
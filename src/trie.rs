@@ -0,0 +1,304 @@
+//! Per-method radix tree used to match static, `:param` and `*wildcard`
+//! route patterns in sub-linear time, à la httprouter/radix-router.
+use std::collections::HashMap;
+
+use worker::Method;
+
+use crate::{BoxedHandler, Params};
+
+/// A captured param name/value pair, accumulated while walking the tree.
+type Captures = Vec<(String, String)>;
+
+/// A radix tree of routes, one tree per HTTP method.
+pub(crate) struct Trie<State> {
+    roots: HashMap<Method, Node<State>>,
+}
+
+impl<State> Default for Trie<State> {
+    fn default() -> Self {
+        Trie {
+            roots: HashMap::new(),
+        }
+    }
+}
+
+impl<State> Trie<State> {
+    pub(crate) fn insert(&mut self, method: Method, path: &str, handler: BoxedHandler<State>) {
+        self.roots
+            .entry(method)
+            .or_default()
+            .insert(path, handler);
+    }
+
+    pub(crate) fn lookup(&self, method: &Method, path: &str) -> Option<(&BoxedHandler<State>, Params)> {
+        let (handler, captures) = self.roots.get(method)?.lookup(path)?;
+        Some((handler, Params::from_captures(captures)))
+    }
+
+    /// Methods for which some route tree matches `path`, regardless of
+    /// whether that method matches the current request.
+    pub(crate) fn methods_matching(&self, path: &str) -> Vec<Method> {
+        self.roots
+            .iter()
+            .filter(|(_, node)| node.lookup(path).is_some())
+            .map(|(method, _)| method.clone())
+            .collect()
+    }
+}
+
+struct Node<State> {
+    /// Literal text shared by every route mounted under this node.
+    prefix: String,
+    /// Static children, matched by their first byte.
+    children: Vec<Node<State>>,
+    param_child: Option<Box<ParamNode<State>>>,
+    wildcard_child: Option<Box<WildcardNode<State>>>,
+    handler: Option<BoxedHandler<State>>,
+}
+
+impl<State> Default for Node<State> {
+    fn default() -> Self {
+        Node {
+            prefix: String::new(),
+            children: Vec::new(),
+            param_child: None,
+            wildcard_child: None,
+            handler: None,
+        }
+    }
+}
+
+struct ParamNode<State> {
+    name: String,
+    node: Node<State>,
+}
+
+struct WildcardNode<State> {
+    name: String,
+    handler: BoxedHandler<State>,
+}
+
+impl<State> Node<State> {
+    fn new_static(prefix: &str) -> Self {
+        Node {
+            prefix: prefix.to_owned(),
+            ..Default::default()
+        }
+    }
+
+    /// Insert `path` (the remaining, unconsumed suffix of the route pattern)
+    /// under this node, splitting existing nodes on common prefixes as needed.
+    fn insert(&mut self, path: &str, handler: BoxedHandler<State>) {
+        if path.is_empty() {
+            self.handler = Some(handler);
+            return;
+        }
+
+        if let Some(rest) = path.strip_prefix(':') {
+            let name_end = rest.find('/').unwrap_or(rest.len());
+            let name = rest[..name_end].to_owned();
+            let remaining = &rest[name_end..];
+
+            let param = self.param_child.get_or_insert_with(|| {
+                Box::new(ParamNode {
+                    name: name.clone(),
+                    node: Node::default(),
+                })
+            });
+            assert_eq!(
+                param.name, name,
+                "conflicting route params at the same path position: `:{}` vs `:{}`",
+                param.name, name
+            );
+            param.node.insert(remaining, handler);
+            return;
+        }
+
+        if let Some(name) = path.strip_prefix('*') {
+            if let Some(existing) = &self.wildcard_child {
+                assert_eq!(
+                    existing.name, name,
+                    "conflicting route wildcards at the same path position: `*{}` vs `*{}`",
+                    existing.name, name
+                );
+            }
+            self.wildcard_child = Some(Box::new(WildcardNode {
+                name: name.to_owned(),
+                handler,
+            }));
+            return;
+        }
+
+        if let Some(child) = self
+            .children
+            .iter_mut()
+            .find(|child| child.prefix.as_bytes().first() == path.as_bytes().first())
+        {
+            let common = common_prefix_len(&child.prefix, path);
+            if common < child.prefix.len() {
+                child.split(common);
+            }
+            child.insert(&path[common..], handler);
+            return;
+        }
+
+        let lit_len = literal_len(path);
+        let mut child = Node::new_static(&path[..lit_len]);
+        child.insert(&path[lit_len..], handler);
+        self.children.push(child);
+    }
+
+    /// Split this node's prefix at `common`, pushing the suffix (and
+    /// everything mounted under it) down into a new child node.
+    fn split(&mut self, common: usize) {
+        let suffix = self.prefix.split_off(common);
+        let moved = Node {
+            prefix: suffix,
+            children: std::mem::take(&mut self.children),
+            param_child: self.param_child.take(),
+            wildcard_child: self.wildcard_child.take(),
+            handler: self.handler.take(),
+        };
+        self.children = vec![moved];
+    }
+
+    /// Walk the tree looking for a handler matching `path`, recording param
+    /// captures as it descends. Static children are tried first, then the
+    /// `:param` child, falling through to the `*wildcard` child last.
+    fn lookup(&self, path: &str) -> Option<(&BoxedHandler<State>, Captures)> {
+        let rest = path.strip_prefix(self.prefix.as_str())?;
+
+        if rest.is_empty() {
+            if let Some(handler) = &self.handler {
+                return Some((handler, Vec::new()));
+            }
+        }
+
+        for child in &self.children {
+            if rest.as_bytes().first() == child.prefix.as_bytes().first() {
+                if let Some(found) = child.lookup(rest) {
+                    return Some(found);
+                }
+            }
+        }
+
+        if let Some(param) = &self.param_child {
+            let end = rest.find('/').unwrap_or(rest.len());
+            if end > 0 {
+                if let Some((handler, mut params)) = param.node.lookup(&rest[end..]) {
+                    params.push((param.name.clone(), rest[..end].to_owned()));
+                    return Some((handler, params));
+                }
+            }
+        }
+
+        if let Some(wildcard) = &self.wildcard_child {
+            if !rest.is_empty() {
+                return Some((
+                    &wildcard.handler,
+                    vec![(wildcard.name.clone(), rest.to_owned())],
+                ));
+            }
+        }
+
+        None
+    }
+}
+
+fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.as_bytes()
+        .iter()
+        .zip(b.as_bytes())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+/// Length of the leading literal run of `path`, stopping right before a
+/// `:param` or `*wildcard` segment so that a new static node never embeds
+/// one of those markers in its prefix.
+fn literal_len(path: &str) -> usize {
+    let bytes = path.as_bytes();
+    for i in 1..bytes.len() {
+        if (bytes[i] == b':' || bytes[i] == b'*') && bytes[i - 1] == b'/' {
+            return i;
+        }
+    }
+    path.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A handler that is only ever compared by address, never called —
+    /// constructing a real `Request` requires a JS/Worker runtime.
+    fn handler() -> BoxedHandler<()> {
+        Box::new(|_req, _state, _params| unreachable!("test handler should never run"))
+    }
+
+    fn addr(h: &BoxedHandler<()>) -> *const () {
+        let fat: *const dyn Fn(worker::Request, std::sync::Arc<()>, Params) -> crate::ResponseFuture =
+            h.as_ref();
+        fat.cast()
+    }
+
+    #[test]
+    fn overlapping_prefixes_split_correctly() {
+        let mut trie = Trie::<()>::default();
+        let users = handler();
+        let users_addr = addr(&users);
+        let user = handler();
+        let user_addr = addr(&user);
+
+        trie.insert(Method::Get, "/users", users);
+        trie.insert(Method::Get, "/user", user);
+
+        let (found, _) = trie.lookup(&Method::Get, "/users").expect("/users should match");
+        assert_eq!(addr(found), users_addr);
+
+        let (found, _) = trie.lookup(&Method::Get, "/user").expect("/user should match");
+        assert_eq!(addr(found), user_addr);
+
+        assert!(trie.lookup(&Method::Get, "/use").is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicting route params")]
+    fn conflicting_param_names_at_same_depth_panic() {
+        let mut trie = Trie::<()>::default();
+        trie.insert(Method::Get, "/a/:x", handler());
+        trie.insert(Method::Get, "/a/:y", handler());
+    }
+
+    #[test]
+    #[should_panic(expected = "conflicting route wildcards")]
+    fn conflicting_wildcard_names_at_same_depth_panic() {
+        let mut trie = Trie::<()>::default();
+        trie.insert(Method::Get, "/files/*path", handler());
+        trie.insert(Method::Get, "/files/*asset", handler());
+    }
+
+    #[test]
+    fn static_route_wins_over_wildcard() {
+        let mut trie = Trie::<()>::default();
+        let logo = handler();
+        let logo_addr = addr(&logo);
+        let catch_all = handler();
+        let catch_all_addr = addr(&catch_all);
+
+        trie.insert(Method::Get, "/files/logo.png", logo);
+        trie.insert(Method::Get, "/files/*path", catch_all);
+
+        let (found, params) = trie
+            .lookup(&Method::Get, "/files/logo.png")
+            .expect("static route should win over the wildcard");
+        assert_eq!(addr(found), logo_addr);
+        assert!(params.get("path").is_none());
+
+        let (found, params) = trie
+            .lookup(&Method::Get, "/files/a/b.png")
+            .expect("wildcard should catch anything else under the prefix");
+        assert_eq!(addr(found), catch_all_addr);
+        assert_eq!(params.get("path"), Some("a/b.png"));
+    }
+}